@@ -24,14 +24,24 @@ OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 */
 
 use util::{b2s};
+use fuse_array::FuseArray;
 
+#[cfg(feature = "std")]
 use std::error;
-use std::error::Error;
-use std::fmt;
-use std::num;
-use std::str;
+use core::fmt;
+use core::num;
+use core::str;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
 
 /// Errors that can occur when parsing a .jed file
+///
+/// No parser lives in this crate yet to populate the offset fields below
+/// from real input; they describe the shape a future parse loop should
+/// fill in. Threading a real offset through requires the `.jed` parse loop
+/// itself, which this crate does not have — that part of the work is out
+/// of scope until a parser exists here to carry it out.
 #[derive(Debug, PartialEq, Eq)]
 pub enum JedParserError {
     /// No STX byte found
@@ -41,7 +51,12 @@ pub enum JedParserError {
     /// An invalid UTF-8 sequence occurred
     InvalidUtf8(str::Utf8Error),
     /// A field contains a character not appropriate for that field (e.g. non-hex digit in a hex field)
-    InvalidCharacter,
+    InvalidCharacter {
+        /// Byte offset into the input where the invalid character was found
+        offset: usize,
+        /// The underlying integer parse failure
+        source: num::ParseIntError,
+    },
     /// An unexpected end of file was encountered in the file checksum
     UnexpectedEnd,
     /// The file checksum was nonzero and incorrect
@@ -49,55 +64,82 @@ pub enum JedParserError {
     /// The fuse checksum (`C` command) was incorrect
     BadFuseChecksum,
     /// A `L` field index was out of range
-    InvalidFuseIndex,
+    InvalidFuseIndex {
+        /// Byte offset into the input where the out-of-range index was found
+        offset: usize,
+        /// The index that was out of range
+        index: usize,
+        /// The `QF` fuse count bounding the valid index range
+        bound: usize,
+    },
     /// There was no `QF` field
     MissingQF,
     /// There was no `F` field, but not all fuses had a value specified
     MissingF,
     /// There was a field that this program does not recognize
-    UnrecognizedField,
+    UnrecognizedField {
+        /// Byte offset into the input where the field began
+        offset: usize,
+        /// The unrecognized field name
+        field: String,
+    },
 }
 
+#[cfg(feature = "std")]
 impl error::Error for JedParserError {
     fn description(&self) -> &'static str {
         match *self {
             JedParserError::MissingSTX => "STX not found",
             JedParserError::MissingETX => "ETX not found",
             JedParserError::InvalidUtf8(_) => "invalid utf8 character",
-            JedParserError::InvalidCharacter => "invalid character in field",
+            JedParserError::InvalidCharacter { .. } => "invalid character in field",
             JedParserError::UnexpectedEnd => "unexpected end of file",
             JedParserError::BadFileChecksum => "invalid file checksum",
             JedParserError::BadFuseChecksum => "invalid fuse checksum",
-            JedParserError::InvalidFuseIndex => "invalid fuse index value",
+            JedParserError::InvalidFuseIndex { .. } => "invalid fuse index value",
             JedParserError::MissingQF => "missing QF field",
             JedParserError::MissingF => "missing F field",
-            JedParserError::UnrecognizedField => "unrecognized field",
+            JedParserError::UnrecognizedField { .. } => "unrecognized field",
         }
     }
 
-    fn cause(&self) -> Option<&error::Error> {
+    fn cause(&self) -> Option<&dyn error::Error> {
         match *self {
             JedParserError::MissingSTX => None,
             JedParserError::MissingETX => None,
             JedParserError::InvalidUtf8(ref err) => Some(err),
-            JedParserError::InvalidCharacter => None,
+            JedParserError::InvalidCharacter { ref source, .. } => Some(source),
             JedParserError::UnexpectedEnd => None,
             JedParserError::BadFileChecksum => None,
             JedParserError::BadFuseChecksum => None,
-            JedParserError::InvalidFuseIndex => None,
+            JedParserError::InvalidFuseIndex { .. } => None,
             JedParserError::MissingQF => None,
             JedParserError::MissingF => None,
-            JedParserError::UnrecognizedField => None,
+            JedParserError::UnrecognizedField { .. } => None,
         }
     }
 }
 
 impl fmt::Display for JedParserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(cause) = self.cause() {
-            write!(f, "{}: {}", self.description(), cause)
-        } else {
-            write!(f, "{}", self.description())
+        match *self {
+            JedParserError::MissingSTX => write!(f, "STX not found"),
+            JedParserError::MissingETX => write!(f, "ETX not found"),
+            JedParserError::InvalidUtf8(ref err) => write!(f, "invalid utf8 character: {}", err),
+            JedParserError::InvalidCharacter { offset, ref source } => {
+                write!(f, "invalid character in field at byte {}: {}", offset, source)
+            },
+            JedParserError::UnexpectedEnd => write!(f, "unexpected end of file"),
+            JedParserError::BadFileChecksum => write!(f, "invalid file checksum"),
+            JedParserError::BadFuseChecksum => write!(f, "invalid fuse checksum"),
+            JedParserError::InvalidFuseIndex { offset, index, bound } => {
+                write!(f, "invalid fuse index {} at byte {} (must be less than {})", index, offset, bound)
+            },
+            JedParserError::MissingQF => write!(f, "missing QF field"),
+            JedParserError::MissingF => write!(f, "missing F field"),
+            JedParserError::UnrecognizedField { offset, ref field } => {
+                write!(f, "unrecognized field \"{}\" at byte {}", field, offset)
+            },
         }
     }
 }
@@ -108,13 +150,21 @@ impl From<str::Utf8Error> for JedParserError {
     }
 }
 
-impl From<num::ParseIntError> for JedParserError {
-    fn from(_: num::ParseIntError) -> Self {
-        JedParserError::InvalidCharacter
-    }
-}
+// There is deliberately no `From<num::ParseIntError> for JedParserError`:
+// `InvalidCharacter`'s `offset` can only be filled in correctly by a parse
+// loop that knows where in the input the failing field started, and no
+// such loop exists in this crate yet. A blanket conversion can only ever
+// fabricate `offset: 0`, which is indistinguishable from a genuine
+// offset-0 error and worse than no conversion at all. Construct
+// `InvalidCharacter` directly once a real parser can supply the offset.
 
 /// Errors that can occur when parsing a bitstream
+///
+/// As with `JedParserError`, no device-matching/decode code in this crate
+/// constructs `WrongFuseCount` yet; `expected`/`found` describe the shape
+/// that code should fill in. Auto-selecting a different device on a count
+/// mismatch, as requested, needs that device-matching code to exist first —
+/// it doesn't in this crate, so that behavior is out of scope here.
 #[derive(Debug, PartialEq, Eq)]
 pub enum XC2BitError {
     /// The .jed file could not be parsed
@@ -122,11 +172,16 @@ pub enum XC2BitError {
     /// The device name is invalid
     BadDeviceName(String),
     /// The number of fuses was incorrect for the device
-    WrongFuseCount,
+    WrongFuseCount {
+        /// The number of fuses the selected device expects
+        expected: usize,
+        /// The number of fuses actually present
+        found: usize,
+    },
     /// An unknown value was used in the `Oe` field
     UnsupportedOeConfiguration((bool, bool, bool, bool)),
     /// An unknown value was used in the ZIA selection bits
-    UnsupportedZIAConfiguration(Vec<bool>),
+    UnsupportedZIAConfiguration(FuseArray),
 }
 
 impl From<JedParserError> for XC2BitError {
@@ -135,22 +190,23 @@ impl From<JedParserError> for XC2BitError {
     }
 }
 
+#[cfg(feature = "std")]
 impl error::Error for XC2BitError {
     fn description(&self) -> &'static str {
         match *self {
             XC2BitError::JedParseError(_) => ".jed parsing failed",
             XC2BitError::BadDeviceName(_) => "device name is invalid/unsupported",
-            XC2BitError::WrongFuseCount => "wrong number of fuses",
+            XC2BitError::WrongFuseCount { .. } => "wrong number of fuses",
             XC2BitError::UnsupportedOeConfiguration(_) => "unknown Oe field value",
             XC2BitError::UnsupportedZIAConfiguration(_) => "unknown ZIA selection bit pattern",
         }
     }
 
-    fn cause(&self) -> Option<&error::Error> {
+    fn cause(&self) -> Option<&dyn error::Error> {
         match *self {
             XC2BitError::JedParseError(ref err) => Some(err),
             XC2BitError::BadDeviceName(_) => None,
-            XC2BitError::WrongFuseCount => None,
+            XC2BitError::WrongFuseCount { .. } => None,
             XC2BitError::UnsupportedOeConfiguration(_) => None,
             XC2BitError::UnsupportedZIAConfiguration(_) => None,
         }
@@ -160,14 +216,14 @@ impl error::Error for XC2BitError {
 impl fmt::Display for XC2BitError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            XC2BitError::JedParseError(_) => {
-                write!(f, "{}: {}", self.description(), self.cause().unwrap())
+            XC2BitError::JedParseError(ref err) => {
+                write!(f, ".jed parsing failed: {}", err)
             },
             XC2BitError::BadDeviceName(ref devname) => {
                 write!(f, "device name \"{}\" is invalid/unsupported", devname)
             },
-            XC2BitError::WrongFuseCount => {
-                write!(f, "{}", self.description())
+            XC2BitError::WrongFuseCount { expected, found } => {
+                write!(f, "wrong number of fuses: expected {}, found {}", expected, found)
             },
             XC2BitError::UnsupportedOeConfiguration(bits) => {
                 write!(f, "unknown Oe field value {}{}{}{}",
@@ -176,7 +232,7 @@ impl fmt::Display for XC2BitError {
             },
             XC2BitError::UnsupportedZIAConfiguration(ref bits) => {
                 write!(f, "unknown ZIA selection bit pattern ")?;
-                for &bit in bits {
+                for bit in bits.iter().by_vals() {
                     write!(f, "{}", b2s(bit))?;
                 }
                 Ok(())
@@ -184,3 +240,28 @@ impl fmt::Display for XC2BitError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    #[test]
+    fn invalid_fuse_index_display_includes_offset_and_bound() {
+        let err = JedParserError::InvalidFuseIndex { offset: 42, index: 100, bound: 64 };
+        assert_eq!(err.to_string(), "invalid fuse index 100 at byte 42 (must be less than 64)");
+    }
+
+    #[test]
+    fn unrecognized_field_display_includes_offset_and_name() {
+        let err = JedParserError::UnrecognizedField { offset: 7, field: "ZZ".to_string() };
+        assert_eq!(err.to_string(), "unrecognized field \"ZZ\" at byte 7");
+    }
+
+    #[test]
+    fn wrong_fuse_count_display_includes_expected_and_found() {
+        let err = XC2BitError::WrongFuseCount { expected: 2592, found: 100 };
+        assert_eq!(err.to_string(), "wrong number of fuses: expected 2592, found 100");
+    }
+}
@@ -0,0 +1,168 @@
+/*
+Copyright (c) 2016-2017, Robert Ou <rqou@robertou.com> and contributors
+All rights reserved.
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+
+1. Redistributions of source code must retain the above copyright notice,
+   this list of conditions and the following disclaimer.
+2. Redistributions in binary form must reproduce the above copyright notice,
+   this list of conditions and the following disclaimer in the documentation
+   and/or other materials provided with the distribution.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+
+//! Serialization of fuse arrays back into the .jed text format.
+
+use std::io;
+use std::io::Write;
+
+use fuse_array::FuseArray;
+
+/// Marks the start of the fuse data transmission
+const STX: u8 = 0x02;
+/// Marks the end of the fuse data transmission
+const ETX: u8 = 0x03;
+
+/// A fuse array plus the device header fields needed to emit a spec-compliant
+/// .jed file
+pub struct JedFile<'a> {
+    /// Free-form text emitted verbatim before the STX byte (design name,
+    /// device name comment, etc.)
+    pub header: &'a str,
+    /// One bit per fuse, in fuse-index order
+    pub fuses: &'a FuseArray,
+    /// Default fuse state (`F` field) shared by every fuse not otherwise
+    /// listed. `None` omits the `F` field.
+    pub default_state: Option<bool>,
+}
+
+impl<'a> JedFile<'a> {
+    /// Serializes this fuse array to `writer` as a spec-compliant .jed stream
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_jed(writer, self.header, self.fuses, self.default_state)
+    }
+}
+
+/// Writes a spec-compliant .jed stream for `fuses` to `writer`.
+///
+/// The body is framed between STX (0x02) and ETX (0x03) and contains the
+/// `QF`, `F`, and `L` fields followed by the `C` fuse checksum. The
+/// transmission checksum is appended after the ETX byte.
+pub fn write_jed<W: Write>(writer: &mut W, header: &str, fuses: &FuseArray,
+    default_state: Option<bool>) -> io::Result<()> {
+
+    let mut body = Vec::new();
+
+    writeln!(body, "QF{}*", fuses.len())?;
+
+    if let Some(default) = default_state {
+        writeln!(body, "F{}*", if default {1} else {0})?;
+    }
+
+    // Emit one L field per contiguous run of equal fuse values rather than
+    // one per fuse
+    let mut i = 0;
+    while i < fuses.len() {
+        let start = i;
+        let value = fuses[i];
+        while i < fuses.len() && fuses[i] == value {
+            i += 1;
+        }
+
+        write!(body, "L{:06} ", start)?;
+        for fuse in fuses[start..i].iter().by_vals() {
+            write!(body, "{}", if fuse {1} else {0})?;
+        }
+        writeln!(body, "*")?;
+    }
+
+    writeln!(body, "C{:04X}*", fuse_checksum(fuses))?;
+
+    writer.write_all(header.as_bytes())?;
+    writer.write_all(&[STX])?;
+    writer.write_all(&body)?;
+    writer.write_all(&[ETX])?;
+
+    write!(writer, "{:04X}", transmission_checksum(&body))?;
+
+    Ok(())
+}
+
+/// Computes the transmission checksum: the low 16 bits of the arithmetic sum
+/// of every byte from STX through ETX inclusive.
+fn transmission_checksum(body: &[u8]) -> u16 {
+    let mut sum: u16 = STX as u16;
+    for &b in body {
+        sum = sum.wrapping_add(b as u16);
+    }
+    sum.wrapping_add(ETX as u16)
+}
+
+/// Computes the `C` fuse checksum: the low 16 bits of the sum of the fuse
+/// bytes formed by packing 8 fuses per byte, LSB-first.
+///
+/// `FuseArray` is already packed this way, so this is a word-wise sum over
+/// its backing storage, except for the last byte: `as_raw_slice` can return
+/// more bits than `len()` reports (e.g. after `truncate`/`resize`/`split_off`
+/// to a length that isn't a multiple of 8), and those dead bits aren't
+/// guaranteed to be zero. The final byte is masked down to the live bits
+/// before being summed.
+fn fuse_checksum(fuses: &FuseArray) -> u16 {
+    let len = fuses.len();
+    let full_bytes = len / 8;
+    let remainder_bits = len % 8;
+
+    let raw = fuses.as_raw_slice();
+    let mut sum: u16 = 0;
+    for &byte in &raw[..full_bytes] {
+        sum = sum.wrapping_add(byte as u16);
+    }
+    if remainder_bits > 0 {
+        let mask = (1u8 << remainder_bits) - 1;
+        sum = sum.wrapping_add((raw[full_bytes] & mask) as u16);
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuse_checksum_ignores_dead_bits_after_truncate() {
+        // truncate()-ing below a byte boundary can leave the now-dead bits
+        // of the last backing byte set; they must not be counted.
+        let mut fuses = FuseArray::repeat(true, 16);
+        fuses.truncate(12);
+        assert_eq!(fuse_checksum(&fuses), 0xFF + 0x0F);
+    }
+
+    #[test]
+    fn write_jed_emits_fields_and_checksums() {
+        let fuses = FuseArray::repeat(false, 10);
+        let mut out = Vec::new();
+        write_jed(&mut out, "", &fuses, Some(false)).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.starts_with("\u{2}QF10*\n"));
+        assert!(text.contains("F0*\n"));
+        assert!(text.contains("C0000*\n"));
+        assert!(text.contains('\u{3}'));
+        // four hex digits for the transmission checksum, right after ETX
+        let after_etx = text.rsplit('\u{3}').next().unwrap();
+        assert_eq!(after_etx.len(), 4);
+        assert!(after_etx.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+}